@@ -58,6 +58,165 @@ pub mod traits {
   pub trait SerdeVia: Serializer+Deserializer {}
 }
 
+/// Base-128 LEB varint encoding, shared by the format tag in [`mux`] and the length prefixes
+/// in [`framing`] and [`formats::tlv`].
+pub(crate) mod varint {
+  use displaydoc::Display;
+  use thiserror::Error;
+
+  /// Error type for failures decoding a base-128 LEB varint.
+  #[derive(Debug, Display, Error)]
+  pub enum VarintDecodeError {
+    /// the input ended before a complete varint was read
+    Truncated,
+    /// the varint's value does not fit in a u64
+    Overflow,
+  }
+
+  /// Encode `value` as a base-128 LEB varint (low 7 bits per byte, high bit set while more
+  /// bytes follow), appending the bytes to `out`.
+  pub fn encode(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+      let mut byte = (value & 0x7f) as u8;
+      value >>= 7;
+      if value != 0 {
+        byte |= 0x80;
+      }
+      out.push(byte);
+      if value == 0 {
+        break;
+      }
+    }
+  }
+
+  /// Decode a base-128 LEB varint from the front of `data`, returning the decoded value
+  /// along with the remaining unconsumed bytes.
+  pub fn decode(data: &[u8]) -> Result<(u64, &[u8]), VarintDecodeError> {
+    let mut result: u64 = 0;
+    for (i, &byte) in data.iter().enumerate() {
+      let shift = 7 * (i as u32);
+      if shift >= 64 {
+        return Err(VarintDecodeError::Overflow);
+      }
+      let low7 = u64::from(byte & 0x7f);
+      // If this byte's 7 bits don't fully fit below bit 64, its high bits must be zero, or
+      // the varint encodes a value too wide for a u64.
+      let avail = 64-shift;
+      if avail<7 && (low7 >> avail) != 0 {
+        return Err(VarintDecodeError::Overflow);
+      }
+      result |= low7 << shift;
+      if byte & 0x80 == 0 {
+        return Ok((result, &data[(i + 1)..]));
+      }
+    }
+    Err(VarintDecodeError::Truncated)
+  }
+}
+
+pub use mux::{FormatTag, Multiplexer, MuxCodingFailure, MuxFormat};
+pub mod mux {
+  use super::{traits::*, varint};
+
+  use displaydoc::Display;
+  use thiserror::Error;
+
+  use std::collections::HashMap;
+
+  /// A small discriminant identifying which registered format produced a given message.
+  pub type FormatTag = u8;
+
+  /// The wire format produced by a [`Multiplexer`]: the active format's [`FormatTag`] encoded
+  /// as a leading varint, followed immediately by that format's own serialized bytes.
+  #[derive(Debug, Copy, Clone)]
+  pub struct MuxFormat;
+
+  impl SerializationFormat for MuxFormat {
+    type Read = [u8];
+    type Written = Box<[u8]>;
+  }
+
+  type BoxedSerialize<Source> = Box<dyn Fn(Source) -> Box<[u8]>>;
+  type BoxedDeserialize<Source> = Box<dyn for<'a> Fn(&'a [u8]) -> Result<Source, MuxCodingFailure>>;
+
+  /// A registry mapping each [`FormatTag`] to the serializer/deserializer pair for some
+  /// concrete `Source` type.
+  ///
+  /// This lets a single byte stream carry whichever format was active when a given message
+  /// was written (protobuf today, CBOR or others tomorrow): a reader decodes any message whose
+  /// leading tag has a format registered under it, and rejects any other tag.
+  pub struct Multiplexer<Source> {
+    formats: HashMap<FormatTag, (BoxedSerialize<Source>, BoxedDeserialize<Source>)>,
+  }
+
+  impl<Source> Default for Multiplexer<Source> {
+    fn default() -> Self { Self { formats: HashMap::new() } }
+  }
+
+  impl<Source> Multiplexer<Source> {
+    pub fn new() -> Self { Self::default() }
+
+    /// Register a format under `tag`, constructing the `F` wrapper from a bare `Source` via
+    /// `ctor` (e.g. `Protobuf::new` or `Cbor::new`) whenever that format is selected to
+    /// serialize a message.
+    pub fn register<F>(&mut self, tag: FormatTag, ctor: impl Fn(Source) -> F+'static)
+    where
+      F: Serializer+Deserializer,
+      F::Fmt: SerializationFormat<Read = [u8], Written = Box<[u8]>>,
+      F::Medium: Schema<Source = Source>,
+      F::Error: std::fmt::Display,
+      Source: 'static,
+    {
+      let serialize: BoxedSerialize<Source> = Box::new(move |source: Source| ctor(source).serialize());
+      let deserialize: BoxedDeserialize<Source> = Box::new(move |data: &[u8]| {
+        F::deserialize(data).map_err(|e| MuxCodingFailure::Inner(tag, e.to_string()))
+      });
+      self.formats.insert(tag, (serialize, deserialize));
+    }
+
+    /// Serialize `source` with the format registered under `tag`, prefixing the result with
+    /// `tag` encoded as a leading varint.
+    pub fn serialize(&self, tag: FormatTag, source: Source) -> Result<Box<[u8]>, MuxCodingFailure> {
+      let (serialize, _) = self
+        .formats
+        .get(&tag)
+        .ok_or(MuxCodingFailure::UnknownFormatTag(tag))?;
+      let payload = serialize(source);
+      let mut out = Vec::with_capacity(payload.len()+1);
+      varint::encode(tag.into(), &mut out);
+      out.extend_from_slice(&payload);
+      Ok(out.into_boxed_slice())
+    }
+
+    /// Read the leading varint tag off `data` and dispatch the remainder to whichever
+    /// deserializer was registered under that tag.
+    pub fn deserialize(&self, data: &[u8]) -> Result<Source, MuxCodingFailure> {
+      let (raw_tag, rest) = varint::decode(data).map_err(MuxCodingFailure::MalformedTag)?;
+      let tag: FormatTag = raw_tag
+        .try_into()
+        .map_err(|_| MuxCodingFailure::TagOutOfRange(raw_tag))?;
+      let (_, deserialize) = self
+        .formats
+        .get(&tag)
+        .ok_or(MuxCodingFailure::UnknownFormatTag(tag))?;
+      deserialize(rest)
+    }
+  }
+
+  /// Error type for specifics on failures to serialize or deserialize via a [`Multiplexer`].
+  #[derive(Debug, Display, Error)]
+  pub enum MuxCodingFailure {
+    /// no format was registered for tag {0}
+    UnknownFormatTag(FormatTag),
+    /// the leading format tag {0} does not fit in a u8
+    TagOutOfRange(u64),
+    /// the leading format tag varint was malformed: {0}
+    MalformedTag(#[from] varint::VarintDecodeError),
+    /// the format registered for tag {0} failed to decode: {1}
+    Inner(FormatTag, String),
+  }
+}
+
 pub mod fingerprinting {
   use super::traits::Schema;
 
@@ -84,6 +243,8 @@ pub mod fingerprinting {
   impl<Source> FingerprintableBytes<Source> {
     pub fn new(bytes: Box<[u8]>) -> Self { Self(bytes, PhantomData) }
 
+    pub fn as_bytes(&self) -> &[u8] { &self.0 }
+
     pub fn from_hex_string(hex_string: &str) -> Result<Self, hex::FromHexError> {
       let decoded: Vec<u8> = hex::decode(hex_string)?;
       Ok(Self::new(decoded.into_boxed_slice()))
@@ -99,11 +260,191 @@ pub mod fingerprinting {
   }
 
   pub trait Fingerprintable: Into<FingerprintableBytes<Self>> {}
+
+  /// A collision-resistant hash applied to the raw bytes of a [`Fingerprintable`] source
+  /// before hex-encoding, so that a [`super::formats::key_fingerprint::KeyFingerprint`] is a
+  /// genuine digest rather than a reversible encoding of the key material itself.
+  pub trait Digest {
+    fn digest(data: &[u8]) -> Box<[u8]>;
+  }
+
+  /// The plain SHA-256 digest, e.g. for a GPG-style fingerprint of serialized key material.
+  #[derive(Debug, Copy, Clone)]
+  pub struct Sha256Digest;
+
+  impl Digest for Sha256Digest {
+    fn digest(data: &[u8]) -> Box<[u8]> {
+      use sha2::Digest as _;
+      sha2::Sha256::digest(data).to_vec().into_boxed_slice()
+    }
+  }
+
+  /// Adapts an inner [`Digest`] `D` to keep only its leading `N` bytes, e.g. to emulate a
+  /// BIP32-style fingerprint (the first 4 bytes of `RIPEMD160(SHA256(pubkey))`) by wrapping, as
+  /// `Truncated<_, 4>`, an inner digest that chains SHA-256 into RIPEMD-160. This crate does not
+  /// provide such an inner digest; the only built-in hash is [`Sha256Digest`].
+  #[derive(Debug, Copy, Clone)]
+  pub struct Truncated<D, const N: usize>(PhantomData<D>);
+
+  impl<D: Digest, const N: usize> Digest for Truncated<D, N> {
+    fn digest(data: &[u8]) -> Box<[u8]> {
+      let full = D::digest(data);
+      full[..full.len().min(N)].to_vec().into_boxed_slice()
+    }
+  }
+
+  /// Marks a [`Digest`] as invertible, i.e. its output uniquely and recoverably encodes its
+  /// input, unlike a cryptographic hash such as [`Sha256Digest`]. Only digests with this bound
+  /// can soundly support parsing a fingerprint back into its source
+  /// (see [`super::formats::key_fingerprint::KeyFingerprint`]'s `Deserializer` impl).
+  pub trait InvertibleDigest: Digest {}
+
+  /// A no-op [`Digest`] that returns its input unchanged, making a `KeyFingerprint` built on
+  /// it a reversible hex encoding rather than a genuine fingerprint. Useful when round-trip
+  /// recovery of `Source` is actually wanted; prefer [`Sha256Digest`] (optionally
+  /// [`Truncated`]) for an actual collision-resistant fingerprint.
+  #[derive(Debug, Copy, Clone)]
+  pub struct IdentityDigest;
+
+  impl Digest for IdentityDigest {
+    fn digest(data: &[u8]) -> Box<[u8]> { data.to_vec().into_boxed_slice() }
+  }
+
+  impl InvertibleDigest for IdentityDigest {}
+
+  /// The inverse of [`Fingerprintable`]: recover a `Self` from the raw bytes backing a
+  /// fingerprint. Only sound when the fingerprint was produced via an [`InvertibleDigest`]; a
+  /// fingerprint hashed with e.g. [`Sha256Digest`] cannot be inverted and should instead be
+  /// checked with [`super::formats::key_fingerprint::KeyFingerprint::verify`].
+  pub trait TryFromFingerprintBytes: Sized {
+    type Error;
+
+    fn try_from_bytes(bytes: FingerprintableBytes<Self>) -> Result<Self, Self::Error>;
+  }
+}
+
+pub use framing::{FramingError, LengthDelimited};
+pub mod framing {
+  use super::{traits::*, varint};
+
+  use displaydoc::Display;
+  use thiserror::Error;
+
+  use std::{io::Read, marker::PhantomData};
+
+  /// Wraps a [`SerializationFormat`] so that several of its messages can be concatenated into,
+  /// and read back out of, a single stream, by prefixing each one with its length.
+  #[derive(Debug, Copy, Clone)]
+  pub struct LengthDelimited<F>(PhantomData<F>);
+
+  impl<F: SerializationFormat<Written = Box<[u8]>>> SerializationFormat for LengthDelimited<F> {
+    type Read = [u8];
+    type Written = Box<[u8]>;
+  }
+
+  impl<F: SerializationFormat<Written = Box<[u8]>>> LengthDelimited<F> {
+    /// Prefix `payload` with its length as a base-128 LEB varint, producing a self-delimiting
+    /// frame that can be appended after any number of other frames in the same stream.
+    pub fn write_frame(payload: F::Written) -> Box<[u8]> {
+      let mut out = Vec::with_capacity(payload.len()+5);
+      varint::encode(payload.len() as u64, &mut out);
+      out.extend_from_slice(&payload);
+      out.into_boxed_slice()
+    }
+  }
+
+  /// Peel varint-length-prefixed frames off the front of `data`, one at a time. Each yielded
+  /// frame is the raw payload for the wrapped format, to be handed to its own
+  /// [`Deserializer`](super::traits::Deserializer).
+  pub fn read_frames(data: &[u8]) -> impl Iterator<Item = Result<&[u8], FramingError>> {
+    Frames { remaining: data }
+  }
+
+  struct Frames<'a> {
+    remaining: &'a [u8],
+  }
+
+  impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a [u8], FramingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      if self.remaining.is_empty() {
+        return None;
+      }
+      Some(self.next_frame())
+    }
+  }
+
+  impl<'a> Frames<'a> {
+    fn next_frame(&mut self) -> Result<&'a [u8], FramingError> {
+      let (len, rest) = varint::decode(self.remaining).map_err(|e| match e {
+        varint::VarintDecodeError::Truncated => FramingError::Truncated,
+        varint::VarintDecodeError::Overflow => FramingError::LengthOverflow,
+      })?;
+      let len: usize = len.try_into().map_err(|_| FramingError::LengthOverflow)?;
+      if rest.len() < len {
+        return Err(FramingError::Truncated);
+      }
+      let (frame, rest) = rest.split_at(len);
+      self.remaining = rest;
+      Ok(frame)
+    }
+  }
+
+  /// Read exactly one varint-length-prefixed frame from `reader`, e.g. a socket or a file
+  /// being tailed, returning its raw payload.
+  ///
+  /// `reader` may be untrusted (the length prefix is attacker-controlled on a socket), so a
+  /// frame claiming to be longer than `max_len` bytes is rejected with
+  /// [`FramingError::FrameTooLarge`] before any payload buffer is allocated, rather than trusting
+  /// the claimed length and allocating it up front.
+  pub fn decode_from<R: Read>(mut reader: R, max_len: usize) -> Result<Box<[u8]>, FramingError> {
+    let mut len: u64 = 0;
+    let mut shift: u32 = 0;
+    loop {
+      let mut byte = [0u8; 1];
+      reader.read_exact(&mut byte).map_err(|_| FramingError::Truncated)?;
+      if shift >= 64 {
+        return Err(FramingError::LengthOverflow);
+      }
+      let low7 = u64::from(byte[0] & 0x7f);
+      let avail = 64-shift;
+      if avail<7 && (low7 >> avail) != 0 {
+        return Err(FramingError::LengthOverflow);
+      }
+      len |= low7 << shift;
+      if byte[0] & 0x80 == 0 {
+        break;
+      }
+      shift += 7;
+    }
+    let len: usize = len.try_into().map_err(|_| FramingError::LengthOverflow)?;
+    if len > max_len {
+      return Err(FramingError::FrameTooLarge { len, max_len });
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).map_err(|_| FramingError::Truncated)?;
+    Ok(payload.into_boxed_slice())
+  }
+
+  /// Error type for specifics on failures to read length-delimited frames.
+  #[derive(Debug, Display, Error)]
+  pub enum FramingError {
+    /// the stream ended before a complete frame could be read
+    Truncated,
+    /// a frame's length prefix does not fit in a usize on this platform
+    LengthOverflow,
+    /// a frame claimed a length of {len}, exceeding the allowed maximum of {max_len}
+    FrameTooLarge { len: usize, max_len: usize },
+  }
 }
 
-pub use formats::key_fingerprint::KeyFingerprint;
+pub use formats::key_fingerprint::{KeyFingerprint, KeyFingerprintCodingFailure};
 #[cfg(feature = "protobuf")]
 pub use formats::protobuf::{Protobuf, ProtobufCodingFailure};
+#[cfg(feature = "cbor")]
+pub use formats::cbor::{Cbor, CborCodingFailure, CborFormat};
+pub use formats::tlv::{Tlv, TlvCodingFailure, TlvFields};
 pub mod formats {
   use super::traits::*;
 
@@ -112,36 +453,88 @@ pub mod formats {
   pub mod key_fingerprint {
     use super::{super::fingerprinting::*, *};
 
+    use displaydoc::Display;
+    use hex;
+    use thiserror::Error;
+
     #[derive(Debug, Copy, Clone)]
-    pub struct KeyFingerprintFormat<Source>(PhantomData<Source>);
+    pub struct KeyFingerprintFormat<Source, D>(PhantomData<(Source, D)>);
 
-    impl<Source> SerializationFormat for KeyFingerprintFormat<Source> {
+    impl<Source, D> SerializationFormat for KeyFingerprintFormat<Source, D> {
       type Read = str;
       type Written = HexFingerprint<Source>;
     }
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-    pub struct KeyFingerprint<Source>(Source);
+    pub struct KeyFingerprint<Source, D>(Source, PhantomData<D>);
 
-    impl<Source> KeyFingerprint<Source> {
-      pub fn new(source: Source) -> Self { Self(source) }
+    impl<Source, D> KeyFingerprint<Source, D> {
+      pub fn new(source: Source) -> Self { Self(source, PhantomData) }
     }
 
-    impl<Source> SerdeViaBase for KeyFingerprint<Source>
-    where Source: Fingerprintable
+    impl<Source, D> SerdeViaBase for KeyFingerprint<Source, D>
+    where
+      Source: Fingerprintable,
+      D: Digest,
     {
-      type Fmt = KeyFingerprintFormat<Source>;
+      type Fmt = KeyFingerprintFormat<Source, D>;
       type Medium = FingerprintableBytes<Source>;
     }
 
-    impl<Source> Serializer for KeyFingerprint<Source>
-    where Source: Fingerprintable
+    impl<Source, D> Serializer for KeyFingerprint<Source, D>
+    where
+      Source: Fingerprintable,
+      D: Digest,
     {
       fn serialize(self) -> HexFingerprint<Source> {
         let proto_message: FingerprintableBytes<_> = self.0.into();
-        proto_message.into_hex_string()
+        let digested = D::digest(proto_message.as_bytes());
+        FingerprintableBytes::<Source>::new(digested).into_hex_string()
+      }
+    }
+
+    /// `KeyFingerprint::deserialize` is only available when `D` is an [`InvertibleDigest`] (e.g.
+    /// [`IdentityDigest`]): for a genuine one-way hash like [`Sha256Digest`], the hex string
+    /// cannot be turned back into a `Source`, and [`KeyFingerprint::verify`] is the sound
+    /// alternative.
+    impl<Source, D> Deserializer for KeyFingerprint<Source, D>
+    where
+      Source: Fingerprintable+TryFromFingerprintBytes,
+      D: InvertibleDigest,
+    {
+      type Error = KeyFingerprintCodingFailure<Source::Error>;
+
+      fn deserialize(data: &str) -> Result<Source, Self::Error> {
+        let bytes = FingerprintableBytes::<Source>::from_hex_string(data)
+          .map_err(KeyFingerprintCodingFailure::Hex)?;
+        Source::try_from_bytes(bytes).map_err(KeyFingerprintCodingFailure::Conversion)
+      }
+    }
+
+    impl<Source, D> KeyFingerprint<Source, D>
+    where
+      Source: Fingerprintable,
+      D: Digest,
+    {
+      /// Recomputes the fingerprint of `candidate` via `D` and compares it against `expected`,
+      /// without attempting to invert `expected` back into a `Source`. This is the sound way to
+      /// check a fingerprint produced by a one-way [`Digest`] such as [`Sha256Digest`], where
+      /// recovering the original source from the hash is not possible.
+      pub fn verify(candidate: Source, expected: &HexFingerprint<Source>) -> bool {
+        let proto_message: FingerprintableBytes<_> = candidate.into();
+        let digested = D::digest(proto_message.as_bytes());
+        let recomputed = FingerprintableBytes::<Source>::new(digested).into_hex_string();
+        recomputed.as_ref() == expected.as_ref()
       }
     }
+
+    #[derive(Debug, Display, Error)]
+    pub enum KeyFingerprintCodingFailure<E> {
+      /// the fingerprint was not valid hex: {0}
+      Hex(#[from] hex::FromHexError),
+      /// the decoded bytes could not be converted into the source type: {0}
+      Conversion(E),
+    }
   }
 
   #[cfg(feature = "protobuf")]
@@ -221,4 +614,418 @@ pub mod formats {
       Decode(#[from] prost::DecodeError),
     }
   }
+
+  #[cfg(feature = "cbor")]
+  pub mod cbor {
+    use super::*;
+
+    use displaydoc::Display;
+    use serde::{de::DeserializeOwned, Serialize};
+    use thiserror::Error;
+
+    #[derive(Debug, Copy, Clone)]
+    pub struct CborFormat;
+
+    impl SerializationFormat for CborFormat {
+      type Read = [u8];
+      type Written = Box<[u8]>;
+    }
+
+    /// Marker [`Schema`] for [`Cbor`] whose `Source` is self-describing and therefore needs no
+    /// separate schema type, unlike [`super::protobuf::Protobuf`]'s generated message type.
+    #[derive(Debug, Copy, Clone)]
+    pub struct CborSchema<Source>(PhantomData<Source>);
+
+    impl<Source> Schema for CborSchema<Source> {
+      type Source = Source;
+    }
+
+    #[derive(Debug, Copy, Clone)]
+    pub struct Cbor<Source>(pub Source);
+
+    impl<Source> Cbor<Source> {
+      pub fn new(source: Source) -> Self { Self(source) }
+    }
+
+    impl<Source> Cbor<Source>
+    where Source: Serialize+DeserializeOwned
+    {
+      /// Like [`Serializer::serialize`], but surfaces a failing `Source::serialize` (e.g. a
+      /// `Serialize` impl that does its own fallible validation and returns a custom
+      /// `serde::ser::Error`) as a [`CborCodingFailure`] instead of panicking. Prefer this
+      /// whenever `Source`'s `Serialize` impl can plausibly fail.
+      pub fn try_serialize(self) -> Result<Box<[u8]>, CborCodingFailure> {
+        Ok(serde_cbor::to_vec(&self.0)?.into_boxed_slice())
+      }
+    }
+
+    impl<Source> SerdeViaBase for Cbor<Source>
+    where Source: Serialize+DeserializeOwned
+    {
+      type Fmt = CborFormat;
+      type Medium = CborSchema<Source>;
+    }
+
+    impl<Source> Serializer for Cbor<Source>
+    where Source: Serialize+DeserializeOwned
+    {
+      /// # Panics
+      /// Panics if `Source::serialize` fails. The [`Serializer`] trait's `serialize` is
+      /// infallible by design, so this impl cannot surface such a failure as a `Result`; use
+      /// [`Cbor::try_serialize`] instead when that matters.
+      fn serialize(self) -> Box<[u8]> {
+        self
+          .try_serialize()
+          .expect("serializing an in-memory value to CBOR should not fail")
+      }
+    }
+
+    impl<Source> Deserializer for Cbor<Source>
+    where Source: Serialize+DeserializeOwned
+    {
+      type Error = CborCodingFailure;
+
+      fn deserialize(data: &[u8]) -> Result<Source, Self::Error> { Ok(serde_cbor::from_slice(data)?) }
+    }
+
+    impl<Source> SerdeVia for Cbor<Source> where Source: Serialize+DeserializeOwned {}
+
+    /// Error type for specifics on failures to serialize or deserialize a CBOR-backed object.
+    #[derive(Debug, Display, Error)]
+    pub enum CborCodingFailure {
+      /// a CBOR (de)serialization error {0} was raised internally
+      Coding(#[from] serde_cbor::Error),
+    }
+  }
+
+  /// Type-length-value encoding of raw key/value pairs, modeled on the PSBT `<keypair>` map
+  /// layout: ordered records of `<key-len><key-type><key-data><value-len><value-data>`,
+  /// terminated by a zero-length key. Self-delimiting and extensible, since an unrecognized
+  /// key type can be skipped over (or preserved) rather than failing the whole decode.
+  pub mod tlv {
+    use super::*;
+    use crate::varint;
+
+    use displaydoc::Display;
+    use thiserror::Error;
+
+    use std::convert::TryInto;
+
+    #[derive(Debug, Copy, Clone)]
+    pub struct TlvFormat;
+
+    impl SerializationFormat for TlvFormat {
+      type Read = [u8];
+      type Written = Box<[u8]>;
+    }
+
+    /// Marker [`Schema`] for [`Tlv`]; the wire layout is entirely described by
+    /// [`TlvFields`], so no separate schema type is needed.
+    #[derive(Debug, Copy, Clone)]
+    pub struct TlvSchema<Source>(PhantomData<Source>);
+
+    impl<Source> Schema for TlvSchema<Source> {
+      type Source = Source;
+    }
+
+    /// Exposes a type's fields as ordered `(key type, key data, value)` triples so that
+    /// [`Tlv`] can encode/decode it as a PSBT-style raw key-value map.
+    ///
+    /// Implementations that want forward-compatible round-tripping should stash any
+    /// `(type, key, value)` triple they don't recognize in an overflow collection on `Self`
+    /// and re-emit it from `to_fields`, so that decoding and re-encoding a message written by
+    /// a newer version of the format does not silently drop its unknown fields.
+    pub trait TlvFields: Sized {
+      type Error;
+
+      fn to_fields(&self) -> Vec<(u8, Box<[u8]>, Box<[u8]>)>;
+
+      fn from_fields(fields: Vec<(u8, Box<[u8]>, Box<[u8]>)>) -> Result<Self, Self::Error>;
+    }
+
+    #[derive(Debug, Copy, Clone)]
+    pub struct Tlv<Source>(pub Source);
+
+    impl<Source> Tlv<Source> {
+      pub fn new(source: Source) -> Self { Self(source) }
+    }
+
+    impl<Source: TlvFields> SerdeViaBase for Tlv<Source> {
+      type Fmt = TlvFormat;
+      type Medium = TlvSchema<Source>;
+    }
+
+    impl<Source: TlvFields> Serializer for Tlv<Source> {
+      fn serialize(self) -> Box<[u8]> {
+        let mut out = Vec::new();
+        for (key_type, key_data, value) in self.0.to_fields() {
+          varint::encode((key_data.len()+1) as u64, &mut out);
+          out.push(key_type);
+          out.extend_from_slice(&key_data);
+          varint::encode(value.len() as u64, &mut out);
+          out.extend_from_slice(&value);
+        }
+        varint::encode(0, &mut out);
+        out.into_boxed_slice()
+      }
+    }
+
+    impl<Source: TlvFields> Deserializer for Tlv<Source> {
+      type Error = TlvCodingFailure<Source::Error>;
+
+      fn deserialize(data: &[u8]) -> Result<Source, Self::Error> {
+        let mut remaining = data;
+        let mut seen = std::collections::HashSet::new();
+        let mut fields = Vec::new();
+
+        loop {
+          let (key_len, rest) =
+            varint::decode(remaining).map_err(|_| TlvCodingFailure::Truncated)?;
+          if key_len == 0 {
+            remaining = rest;
+            break;
+          }
+          let key_len: usize = key_len.try_into().map_err(|_| TlvCodingFailure::LengthOverflow)?;
+          if rest.len() < key_len {
+            return Err(TlvCodingFailure::Truncated);
+          }
+          let (key, rest) = rest.split_at(key_len);
+          let (&key_type, key_data) = key.split_first().ok_or(TlvCodingFailure::Truncated)?;
+
+          let (value_len, rest) = varint::decode(rest).map_err(|_| TlvCodingFailure::Truncated)?;
+          let value_len: usize =
+            value_len.try_into().map_err(|_| TlvCodingFailure::LengthOverflow)?;
+          if rest.len() < value_len {
+            return Err(TlvCodingFailure::Truncated);
+          }
+          let (value, rest) = rest.split_at(value_len);
+
+          if !seen.insert((key_type, key_data.to_vec())) {
+            return Err(TlvCodingFailure::DuplicateKey(key_type));
+          }
+          fields.push((key_type, key_data.to_vec().into_boxed_slice(), value.to_vec().into_boxed_slice()));
+          remaining = rest;
+        }
+
+        if !remaining.is_empty() {
+          return Err(TlvCodingFailure::TrailingData);
+        }
+
+        Source::from_fields(fields).map_err(TlvCodingFailure::Conversion)
+      }
+    }
+
+    impl<Source: TlvFields> SerdeVia for Tlv<Source> {}
+
+    /// Error type for specifics on failures to serialize or deserialize a TLV-backed object.
+    #[derive(Debug, Display, Error)]
+    pub enum TlvCodingFailure<E> {
+      /// the TLV stream ended before a complete record could be read
+      Truncated,
+      /// a TLV length prefix does not fit in a usize on this platform
+      LengthOverflow,
+      /// a duplicate entry for key type {0} was encountered
+      DuplicateKey(u8),
+      /// bytes remained after the zero-length key terminating the TLV map
+      TrailingData,
+      /// the decoded fields could not be converted into the source type: {0}
+      Conversion(E),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    fingerprinting::{Fingerprintable, FingerprintableBytes, IdentityDigest, Sha256Digest},
+    formats::tlv::TlvFormat,
+    framing::read_frames,
+    varint,
+  };
+
+  use std::convert::TryInto;
+
+  #[test]
+  fn varint_round_trips() {
+    for value in [0u64, 1, 127, 128, 300, u64::MAX] {
+      let mut buf = Vec::new();
+      varint::encode(value, &mut buf);
+      let (decoded, rest) = varint::decode(&buf).unwrap();
+      assert_eq!(decoded, value);
+      assert!(rest.is_empty());
+    }
+  }
+
+  #[test]
+  fn varint_rejects_overflowing_final_byte() {
+    let mut buf = vec![0xffu8; 9];
+    buf.push(0x7f);
+    assert!(matches!(varint::decode(&buf), Err(varint::VarintDecodeError::Overflow)));
+  }
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[cfg_attr(feature = "cbor", derive(serde::Serialize, serde::Deserialize))]
+  struct Widget {
+    value: u32,
+  }
+
+  impl Fingerprintable for Widget {}
+  impl From<Widget> for FingerprintableBytes<Widget> {
+    fn from(value: Widget) -> Self { FingerprintableBytes::new(value.value.to_be_bytes().into()) }
+  }
+
+  #[test]
+  fn key_fingerprint_serializes_to_hex_digest() {
+    let fingerprint = KeyFingerprint::<Widget, Sha256Digest>::new(Widget { value: 42 });
+    let hex: String = fingerprint.serialize().into();
+    assert_eq!(hex.len(), 64);
+  }
+
+  #[test]
+  fn key_fingerprint_verifies_without_inverting() {
+    let widget = Widget { value: 42 };
+    let fingerprint = KeyFingerprint::<Widget, Sha256Digest>::new(widget).serialize();
+    assert!(KeyFingerprint::<Widget, Sha256Digest>::verify(widget, &fingerprint));
+    assert!(!KeyFingerprint::<Widget, Sha256Digest>::verify(Widget { value: 43 }, &fingerprint));
+  }
+
+  impl crate::fingerprinting::TryFromFingerprintBytes for Widget {
+    type Error = std::array::TryFromSliceError;
+
+    fn try_from_bytes(bytes: FingerprintableBytes<Self>) -> Result<Self, Self::Error> {
+      let value = u32::from_be_bytes(bytes.as_bytes().try_into()?);
+      Ok(Widget { value })
+    }
+  }
+
+  #[test]
+  fn key_fingerprint_round_trips_via_identity_digest() {
+    let widget = Widget { value: 99 };
+    let hex: String = KeyFingerprint::<Widget, IdentityDigest>::new(widget).serialize().into();
+    let decoded: Widget = KeyFingerprint::<Widget, IdentityDigest>::deserialize(&hex).unwrap();
+    assert_eq!(decoded, widget);
+  }
+
+  impl TlvFields for Widget {
+    type Error = std::convert::Infallible;
+
+    fn to_fields(&self) -> Vec<(u8, Box<[u8]>, Box<[u8]>)> {
+      vec![(0, Vec::new().into_boxed_slice(), self.value.to_be_bytes().to_vec().into_boxed_slice())]
+    }
+
+    fn from_fields(mut fields: Vec<(u8, Box<[u8]>, Box<[u8]>)>) -> Result<Self, Self::Error> {
+      let (_, _, value) = fields.remove(0);
+      let bytes: [u8; 4] = value.as_ref().try_into().unwrap();
+      Ok(Widget { value: u32::from_be_bytes(bytes) })
+    }
+  }
+
+  #[test]
+  fn tlv_round_trips() {
+    let widget = Widget { value: 7 };
+    let bytes = Tlv::new(widget).serialize();
+    let decoded: Widget = Tlv::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, widget);
+  }
+
+  #[test]
+  fn tlv_rejects_trailing_data() {
+    let mut bytes = Tlv::new(Widget { value: 7 }).serialize().to_vec();
+    bytes.push(0xaa);
+    let err = Tlv::<Widget>::deserialize(&bytes).unwrap_err();
+    assert!(matches!(err, TlvCodingFailure::TrailingData));
+  }
+
+  #[test]
+  fn tlv_rejects_duplicate_keys() {
+    let mut buf = Vec::new();
+    for _ in 0..2 {
+      varint::encode(1, &mut buf);
+      buf.push(5);
+      varint::encode(0, &mut buf);
+    }
+    varint::encode(0, &mut buf);
+    let err = Tlv::<Widget>::deserialize(&buf).unwrap_err();
+    assert!(matches!(err, TlvCodingFailure::DuplicateKey(5)));
+  }
+
+  #[test]
+  fn mux_round_trips_tagged_format() {
+    let mut mux = Multiplexer::<Widget>::new();
+    mux.register(1u8, Tlv::new);
+    let bytes = mux.serialize(1, Widget { value: 99 }).unwrap();
+    let decoded = mux.deserialize(&bytes).unwrap();
+    assert_eq!(decoded, Widget { value: 99 });
+  }
+
+  #[test]
+  fn mux_rejects_unknown_format_tag() {
+    let mut mux = Multiplexer::<Widget>::new();
+    mux.register(1u8, Tlv::new);
+    let err = mux.serialize(2, Widget { value: 99 }).unwrap_err();
+    assert!(matches!(err, MuxCodingFailure::UnknownFormatTag(2)));
+
+    let mut tagged_for_unregistered = Vec::new();
+    varint::encode(2, &mut tagged_for_unregistered);
+    let err = mux.deserialize(&tagged_for_unregistered).unwrap_err();
+    assert!(matches!(err, MuxCodingFailure::UnknownFormatTag(2)));
+  }
+
+  #[test]
+  fn mux_rejects_tag_out_of_range() {
+    let mux = Multiplexer::<Widget>::new();
+    let mut oversized_tag = Vec::new();
+    varint::encode(300, &mut oversized_tag);
+    let err = mux.deserialize(&oversized_tag).unwrap_err();
+    assert!(matches!(err, MuxCodingFailure::TagOutOfRange(300)));
+  }
+
+  #[cfg(feature = "cbor")]
+  #[test]
+  fn cbor_round_trips() {
+    let widget = Widget { value: 7 };
+    let bytes = Cbor::new(widget).serialize();
+    let decoded: Widget = Cbor::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, widget);
+  }
+
+  #[cfg(feature = "cbor")]
+  #[test]
+  fn cbor_try_serialize_round_trips() {
+    let widget = Widget { value: 7 };
+    let bytes = Cbor::new(widget).try_serialize().unwrap();
+    let decoded: Widget = Cbor::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, widget);
+  }
+
+  #[test]
+  fn framing_round_trips_multiple_frames() {
+    let mut stream = Vec::new();
+    stream.extend_from_slice(&LengthDelimited::<TlvFormat>::write_frame(
+      Tlv::new(Widget { value: 1 }).serialize(),
+    ));
+    stream.extend_from_slice(&LengthDelimited::<TlvFormat>::write_frame(
+      Tlv::new(Widget { value: 2 }).serialize(),
+    ));
+
+    let frames: Vec<&[u8]> = read_frames(&stream).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(Tlv::<Widget>::deserialize(frames[0]).unwrap(), Widget { value: 1 });
+    assert_eq!(Tlv::<Widget>::deserialize(frames[1]).unwrap(), Widget { value: 2 });
+  }
+
+  #[test]
+  fn framing_decode_from_round_trips() {
+    let frame = LengthDelimited::<TlvFormat>::write_frame(Tlv::new(Widget { value: 3 }).serialize());
+    let payload = framing::decode_from(std::io::Cursor::new(frame.as_ref()), 1024).unwrap();
+    assert_eq!(Tlv::<Widget>::deserialize(&payload).unwrap(), Widget { value: 3 });
+  }
+
+  #[test]
+  fn framing_decode_from_rejects_oversized_frame() {
+    let frame = LengthDelimited::<TlvFormat>::write_frame(Tlv::new(Widget { value: 3 }).serialize());
+    let err = framing::decode_from(std::io::Cursor::new(frame.as_ref()), 1).unwrap_err();
+    assert!(matches!(err, crate::framing::FramingError::FrameTooLarge { max_len: 1, .. }));
+  }
 }